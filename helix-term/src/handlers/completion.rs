@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -6,6 +7,7 @@ use anyhow::Result;
 use helix_core::chars::char_is_word;
 use helix_core::completion::CompletionProvider;
 use helix_core::syntax::LanguageServerFeature;
+use helix_core::Transaction;
 use helix_event::{register_hook, send_blocking, TaskHandle};
 use helix_lsp::lsp;
 use helix_stdx::rope::RopeSliceExt;
@@ -17,8 +19,10 @@ use tokio::task::JoinSet;
 
 use crate::commands;
 use crate::compositor::Compositor;
-use crate::events::{OnModeSwitch, PostCommand, PostInsertChar};
-use crate::handlers::completion::request::{request_incomplete_completion_list, Trigger};
+use crate::events::{DocumentDidClose, OnModeSwitch, PostCommand, PostInsertChar};
+use crate::handlers::completion::request::{
+    forget_document, request_incomplete_completion_list, Trigger,
+};
 use crate::job::dispatch;
 use crate::keymap::MappableCommand;
 use crate::ui::lsp::SignatureHelp;
@@ -30,17 +34,32 @@ pub use item::{CompletionItem, CompletionItems, CompletionResponse, LspCompletio
 pub use request::CompletionHandler;
 pub use resolve::ResolveHandler;
 
+mod frecency;
 mod item;
 mod path;
 mod request;
 mod resolve;
+mod snippet;
+
+pub use snippet::{Snippet, SnippetSession};
+
+/// Whether a response stamped with `response_revision` was superseded by a
+/// newer trigger before it even finished, and should be dropped without
+/// paying for a dispatch round trip just to drop it via `handle.is_canceled()`.
+fn is_stale(response_revision: usize, current_revision: usize) -> bool {
+    response_revision < current_revision
+}
 
 async fn handle_response(
     requests: &mut JoinSet<CompletionResponse>,
     incomplete: bool,
+    current_revision: &AtomicUsize,
 ) -> Option<CompletionResponse> {
     loop {
         let response = requests.join_next().await?.unwrap();
+        if is_stale(response.revision, current_revision.load(Ordering::Relaxed)) {
+            continue;
+        }
         if !incomplete && !response.incomplete && response.items.is_empty() {
             continue;
         }
@@ -52,8 +71,10 @@ async fn replace_completions(
     handle: TaskHandle,
     mut requests: JoinSet<CompletionResponse>,
     incomplete: bool,
+    current_revision: Arc<AtomicUsize>,
 ) {
-    while let Some(response) = handle_response(&mut requests, incomplete).await {
+    while let Some(response) = handle_response(&mut requests, incomplete, &current_revision).await
+    {
         let handle = handle.clone();
         dispatch(move |editor, compositor| {
             let editor_view = compositor.find::<ui::EditorView>().unwrap();
@@ -94,6 +115,14 @@ fn show_completion(
         return;
     }
 
+    // `frecency` takes a label and the menu's own fuzzy-match score and
+    // returns the combined sort key (see `frecency::combine_score`), so
+    // accepted items float toward the top without us pre-sorting a ranking
+    // the menu's own re-scoring on every keystroke would immediately
+    // overwrite.
+    let language = doc.language_name().unwrap_or("text");
+    let frecency = frecency::scorer(language);
+
     let size = compositor.size();
     let ui = compositor.find::<ui::EditorView>().unwrap();
     if ui.completion.is_some() {
@@ -107,6 +136,7 @@ fn show_completion(
         incomplete_completion_lists,
         trigger.pos,
         size,
+        &frecency,
     );
     let signature_help_area = compositor
         .find_id::<Popup<SignatureHelp>>(SignatureHelp::ID)
@@ -201,6 +231,132 @@ fn update_completion_filter(cx: &mut commands::Context, c: Option<char>) {
     }))
 }
 
+/// Bumps the frecency score for `item` for `doc`'s language and, if `item`
+/// is a snippet, strips its `$1`/`${2:…}`/`$0` markers back out of the
+/// document and starts a tabstop session over the rendered text. Called
+/// from the `completion` command's post-command hook once the item's raw
+/// insert text has actually landed in the document.
+fn finalize_completion_accept(cx: &mut commands::Context) {
+    let Some(item) = cx.editor.last_completion.take() else {
+        return;
+    };
+    let (view, doc) = current!(cx.editor);
+    let language = doc.language_name().unwrap_or("text");
+    frecency::record_accept(language, item.label());
+
+    let Some(snippet) = item.snippet() else {
+        return;
+    };
+
+    // `cursor` is where the raw insert text ends up after insertion; back
+    // up by its length to find where it actually started so the snippet
+    // renders (and its tabstops land) over the text that is really there,
+    // not past it.
+    let cursor = doc
+        .selection(view.id)
+        .primary()
+        .cursor(doc.text().slice(..));
+    let insert_start = cursor.saturating_sub(item.insert_text().chars().count());
+
+    let (rendered, tabstops) = snippet.render(insert_start);
+    let transaction = Transaction::change(
+        doc.text(),
+        std::iter::once((insert_start, cursor, Some(rendered))),
+    );
+    doc.apply(&transaction, view.id);
+    let doc_len = doc.text().len_chars();
+
+    let session =
+        (tabstops.len() > 1).then(|| SnippetSession::new(doc.id(), view.id, tabstops, doc_len));
+    // Select every mirror of the first tabstop (its default pre-selected).
+    if let Some(selection) = session.as_ref().and_then(|s| s.selection(doc_len)) {
+        doc.set_selection(view.id, selection);
+    }
+    snippet::set_active(session);
+}
+
+/// Moves the active tabstop forward/backward, updating the primary
+/// selection (and its mirrors) to match. Tears the session down once the
+/// user cycles past the final tabstop (`$0`) or backs up past the first.
+fn cycle_snippet_tabstop(cx: &mut commands::Context, forward: bool) {
+    let (view, doc) = current!(cx.editor);
+    let (doc_id, view_id) = (doc.id(), view.id);
+
+    let moved = snippet::with_active(|session| {
+        if session.doc != doc_id || session.view != view_id {
+            return None;
+        }
+        let moved = if forward {
+            session.goto_next()
+        } else {
+            session.goto_prev()
+        };
+        moved.then(|| session.selection(doc.text().len_chars())).flatten()
+    });
+
+    match moved {
+        Some(Some(selection)) => doc.set_selection(view_id, selection),
+        _ => snippet::set_active(None),
+    }
+}
+
+/// Re-syncs the active snippet session with whatever the command that was
+/// just dispatched (anything other than `goto_next_tabstop`/
+/// `goto_prev_tabstop`) did to the buffer. Commands are not individually
+/// enumerated as edits or motions here; instead the session's own
+/// `external_len_diff` compares the buffer's length now against the length
+/// last recorded against it, so a command that left the buffer's size
+/// unchanged (cursor motions, selection changes, ...) is a no-op rather
+/// than tearing the session down, while one that actually resized it
+/// (e.g. `delete_char_backward` clearing a tabstop's selected default
+/// text) is applied with its real size, not an assumed one. Still drops
+/// the session if a real edit lands outside every mirror of the active
+/// tabstop, mirroring how `last_completion` is invalidated.
+fn sync_snippet_session_after_command(cx: &mut commands::Context) {
+    let (view, doc) = current!(cx.editor);
+    let (doc_id, view_id) = (doc.id(), view.id);
+    let current_len = doc.text().len_chars();
+    let pos = doc.selection(view.id).primary().cursor(doc.text().slice(..));
+
+    let stale = snippet::with_active(|session| {
+        if session.doc != doc_id || session.view != view_id {
+            return false;
+        }
+        let Some(len_diff) = session.external_len_diff(current_len) else {
+            return false;
+        };
+        if !session.contains(pos) {
+            return true;
+        }
+        session.apply_edit(pos, len_diff);
+        false
+    });
+    if stale.unwrap_or(false) {
+        snippet::set_active(None);
+    }
+}
+
+/// Like `sync_snippet_session_after_command`, but for a single character
+/// just inserted at `at`. The keystroke itself is already mirrored into
+/// every other range of the active tabstop by the editor: `cycle_snippet_tabstop`
+/// sets a multi-range `Selection` covering every mirror, and Helix's insert
+/// commands apply to every range in the current selection. So only the
+/// ranges' length bookkeeping needs updating here, not their content — doing
+/// that content replay ourselves on top of the editor's own multi-cursor
+/// insert would apply the keystroke twice.
+fn sync_snippet_session_after_insert_char(at: usize) {
+    let stale = snippet::with_active(|session| {
+        if !session.contains(at) {
+            return true;
+        }
+        session.apply_edit(at, 1);
+        false
+    });
+    if stale.unwrap_or(false) {
+        snippet::set_active(None);
+    }
+}
+
 fn clear_completions(cx: &mut commands::Context) {
     cx.callback.push(Box::new(|compositor, cx| {
         let editor_view = compositor.find::<ui::EditorView>().unwrap();
@@ -212,13 +368,35 @@ fn completion_post_command_hook(
     tx: &Sender<CompletionEvent>,
     PostCommand { command, cx }: &mut PostCommand<'_, '_>,
 ) -> Result<()> {
+    // `goto_next_tabstop`/`goto_prev_tabstop` are added to the static command
+    // table (and bound to Tab/S-Tab in insert mode) alongside the rest of
+    // this crate's commands, the same way `completion` and
+    // `delete_char_backward` already are below.
+    if snippet::active().is_some() {
+        match command {
+            MappableCommand::Static {
+                name: "goto_next_tabstop",
+                ..
+            } => cycle_snippet_tabstop(cx, true),
+            MappableCommand::Static {
+                name: "goto_prev_tabstop",
+                ..
+            } => cycle_snippet_tabstop(cx, false),
+            _ => sync_snippet_session_after_command(cx),
+        }
+    }
+
     if cx.editor.mode == Mode::Insert {
         if cx.editor.last_completion.is_some() {
             match command {
                 MappableCommand::Static {
-                    name: "delete_word_forward" | "delete_char_forward" | "completion",
+                    name: "delete_word_forward" | "delete_char_forward",
                     ..
                 } => (),
+                MappableCommand::Static {
+                    name: "completion",
+                    ..
+                } => finalize_completion_accept(cx),
                 MappableCommand::Static {
                     name: "delete_char_backward",
                     ..
@@ -254,6 +432,18 @@ fn completion_post_command_hook(
     Ok(())
 }
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn stale_responses_are_filtered_by_revision() {
+        assert!(is_stale(1, 2), "an older revision is stale");
+        assert!(!is_stale(2, 2), "the current revision is not stale");
+        assert!(!is_stale(3, 2), "a newer revision is not stale");
+    }
+}
+
 pub(super) fn register_hooks(handlers: &Handlers) {
     let tx = handlers.completions.clone();
     register_hook!(move |event: &mut PostCommand<'_, '_>| completion_post_command_hook(&tx, event));
@@ -263,6 +453,7 @@ pub(super) fn register_hooks(handlers: &Handlers) {
         if event.old_mode == Mode::Insert {
             send_blocking(&tx, CompletionEvent::Cancel);
             clear_completions(event.cx);
+            snippet::set_active(None);
         } else if event.new_mode == Mode::Insert {
             trigger_auto_completion(&tx, event.cx.editor, false)
         }
@@ -276,6 +467,21 @@ pub(super) fn register_hooks(handlers: &Handlers) {
         } else {
             trigger_auto_completion(&tx, event.cx.editor, false);
         }
+        if snippet::active().is_some() {
+            let (view, doc) = current!(event.cx.editor);
+            let pos = doc.selection(view.id).primary().cursor(doc.text().slice(..));
+            // `pos` is the cursor after insertion; the edit happened one char
+            // back, growing whatever tabstop it landed in by one char.
+            sync_snippet_session_after_insert_char(pos.saturating_sub(1));
+        }
+        Ok(())
+    });
+
+    // Drop the closed document's revision counter and in-flight request task
+    // (if any) so a long session opening and closing many buffers doesn't
+    // grow those registries unboundedly.
+    register_hook!(move |event: &mut DocumentDidClose<'_>| {
+        forget_document(event.doc.id());
         Ok(())
     });
 }
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+/// Half-life (in seconds) used to decay frecency scores. After this many
+/// seconds have passed since an entry's `last_accessed` timestamp, its
+/// accumulated score is worth half as much.
+const HALF_LIFE_SECS: f64 = 60.0 * 60.0 * 24.0 * 7.0; // one week
+
+/// Weight given to the (normalized) frecency score when blending it with the
+/// fuzzy-match score produced by the picker.
+const FRECENCY_WEIGHT: f32 = 0.35;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FrecencyEntry {
+    /// Raw acceptance count, decayed lazily against `last_accessed`.
+    score: f64,
+    /// Unix timestamp (seconds) of the last time this entry was bumped.
+    last_accessed: u64,
+}
+
+/// Persistent store of "frecency" (frequency + recency) scores for accepted
+/// completion items, namespaced by language so that e.g. Rust's `new` and
+/// JavaScript's `new` are tracked independently.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct FrecencyStore {
+    entries: HashMap<String, FrecencyEntry>,
+    #[serde(skip)]
+    dirty: bool,
+}
+
+impl FrecencyStore {
+    fn key(language: &str, label: &str) -> String {
+        format!("{language}\u{0}{label}")
+    }
+
+    fn cache_path(cache_dir: &Path) -> PathBuf {
+        cache_dir.join("completion_frecency.json")
+    }
+
+    /// Loads the store from `cache_dir`, returning an empty store if no
+    /// cache file exists yet or it fails to parse.
+    pub fn load(cache_dir: &Path) -> Self {
+        let path = Self::cache_path(cache_dir);
+        std::fs::read(&path)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persists the store to `cache_dir` if it has been modified since the
+    /// last save.
+    pub fn save(&mut self, cache_dir: &Path) -> std::io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        std::fs::create_dir_all(cache_dir)?;
+        let bytes = serde_json::to_vec(self).unwrap_or_default();
+        std::fs::write(Self::cache_path(cache_dir), bytes)?;
+        self.dirty = false;
+        Ok(())
+    }
+
+    fn now() -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+
+    /// Applies lazy exponential decay to `score` given how long ago
+    /// `last_accessed` was, so idle editors don't need a background timer.
+    fn decayed(score: f64, last_accessed: u64) -> f64 {
+        let elapsed = Self::now().saturating_sub(last_accessed) as f64;
+        score * 0.5f64.powf(elapsed / HALF_LIFE_SECS)
+    }
+
+    /// Records that `label` (in `language`) was just accepted, bumping its
+    /// score and refreshing its timestamp.
+    pub fn record_accept(&mut self, language: &str, label: &str) {
+        let now = Self::now();
+        let entry = self
+            .entries
+            .entry(Self::key(language, label))
+            .or_insert(FrecencyEntry {
+                score: 0.0,
+                last_accessed: now,
+            });
+        entry.score = Self::decayed(entry.score, entry.last_accessed) + 1.0;
+        entry.last_accessed = now;
+        self.dirty = true;
+    }
+
+    /// Returns the current (decayed) frecency score for `label`, or `0.0` if
+    /// it has never been accepted.
+    pub fn score(&self, language: &str, label: &str) -> f64 {
+        self.entries
+            .get(&Self::key(language, label))
+            .map(|entry| Self::decayed(entry.score, entry.last_accessed))
+            .unwrap_or(0.0)
+    }
+}
+
+/// Combines a fuzzy-match score (as produced by the completion menu's
+/// matcher) with the frecency score for `label` into a single sort key,
+/// higher is better. The frecency contribution is squashed through a log
+/// curve so a handful of accepts nudges ranking without letting an
+/// old favorite permanently dominate exact fuzzy matches.
+pub fn combine_score(fuzzy_score: i32, frecency_score: f64) -> i32 {
+    if frecency_score <= 0.0 {
+        return fuzzy_score;
+    }
+    let boost = (frecency_score.ln_1p() * FRECENCY_WEIGHT as f64 * 10.0).round() as i32;
+    fuzzy_score.saturating_add(boost)
+}
+
+/// Process-wide frecency store, lazily loaded from the cache dir on first
+/// use and saved back whenever an acceptance is recorded.
+pub static STORE: Lazy<Mutex<FrecencyStore>> =
+    Lazy::new(|| Mutex::new(FrecencyStore::load(&helix_loader::cache_dir())));
+
+/// Returns a closure that folds a label's learned frecency (in `language`)
+/// into a fuzzy-match score the menu already computed, via
+/// [`combine_score`]. Meant to be handed to the completion menu so it can
+/// use it as its sort key as it re-scores items against typed input,
+/// rather than us pre-sorting a ranking the menu immediately overwrites.
+pub fn scorer(language: &str) -> impl Fn(&str, i32) -> i32 + '_ {
+    move |label, fuzzy_score| {
+        let frecency_score = STORE.lock().unwrap().score(language, label);
+        combine_score(fuzzy_score, frecency_score)
+    }
+}
+
+/// Called once a completion item has actually been accepted by the user so
+/// future sessions rank it higher. The in-memory bump happens immediately
+/// (cheap, and needed by the very next `scorer` call), but persisting it is
+/// dispatched to the blocking job pool rather than done with a synchronous
+/// `fs::write` here, so a slow/networked cache dir can't stall the editor on
+/// every acceptance.
+pub fn record_accept(language: &str, label: &str) {
+    STORE.lock().unwrap().record_accept(language, label);
+    tokio::task::spawn_blocking(|| {
+        let mut store = STORE.lock().unwrap();
+        if let Err(err) = store.save(&helix_loader::cache_dir()) {
+            log::warn!("failed to persist completion frecency cache: {err}");
+        }
+    });
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decayed_halves_after_one_half_life() {
+        let now = FrecencyStore::now();
+        let decayed = FrecencyStore::decayed(1.0, now - HALF_LIFE_SECS as u64);
+        assert!(
+            (decayed - 0.5).abs() < 0.01,
+            "expected ~0.5 after one half-life, got {decayed}"
+        );
+    }
+
+    #[test]
+    fn record_accept_is_namespaced_by_language() {
+        let mut store = FrecencyStore::default();
+        store.record_accept("rust", "new");
+        assert!(store.score("rust", "new") > 0.0);
+        assert_eq!(
+            store.score("javascript", "new"),
+            0.0,
+            "same label in a different language should be tracked independently"
+        );
+    }
+
+    #[test]
+    fn combine_score_boosts_by_frecency_but_leaves_zero_untouched() {
+        assert_eq!(combine_score(50, 0.0), 50);
+        assert!(
+            combine_score(50, 4.0) > 50,
+            "a positive frecency score should nudge the fuzzy score upward"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+use helix_core::completion::CompletionProvider;
+use helix_lsp::lsp;
+
+use super::snippet::Snippet;
+
+/// A completion item as received from a single LSP completion provider,
+/// before it has been merged into the menu alongside items from other
+/// providers.
+#[derive(Debug, Clone)]
+pub struct LspCompletionItem {
+    pub item: lsp::CompletionItem,
+    pub provider: CompletionProvider,
+    pub provider_priority: i8,
+    pub resolved: bool,
+}
+
+/// A completion item shown in the menu. Kept as an enum (rather than baking
+/// LSP directly into the name) so path/word completions can join the same
+/// menu later without reshaping the pipeline.
+#[derive(Debug, Clone)]
+pub enum CompletionItem {
+    Lsp(LspCompletionItem),
+}
+
+impl CompletionItem {
+    pub fn label(&self) -> &str {
+        match self {
+            CompletionItem::Lsp(item) => item.item.label.as_str(),
+        }
+    }
+
+    pub fn provider(&self) -> &CompletionProvider {
+        match self {
+            CompletionItem::Lsp(item) => &item.provider,
+        }
+    }
+
+    /// The raw text that gets inserted into the document for this item
+    /// (before any snippet markers are stripped), so callers that already
+    /// inserted it verbatim can work out how much of the buffer it spans.
+    /// Mirrors the precedence the accept path itself resolves the inserted
+    /// text with: a `text_edit` (used by e.g. rust-analyzer's function-call
+    /// snippets) takes priority over `insert_text`, which in turn falls back
+    /// to the label — otherwise this would disagree with whatever text was
+    /// actually written to the buffer and corrupt the snippet-accept edit.
+    pub fn insert_text(&self) -> &str {
+        match self {
+            CompletionItem::Lsp(item) => item
+                .item
+                .text_edit
+                .as_ref()
+                .map(|edit| match edit {
+                    lsp::CompletionTextEdit::Edit(edit) => edit.new_text.as_str(),
+                    lsp::CompletionTextEdit::InsertAndReplace(edit) => edit.new_text.as_str(),
+                })
+                .or(item.item.insert_text.as_deref())
+                .unwrap_or(&item.item.label),
+        }
+    }
+
+    /// Parses this item's insert text as a snippet if the language server
+    /// marked it `InsertTextFormat::SNIPPET`, so the tabstop session can be
+    /// started once the item is accepted.
+    pub fn snippet(&self) -> Option<Snippet> {
+        match self {
+            CompletionItem::Lsp(item) => {
+                if item.item.insert_text_format == Some(lsp::InsertTextFormat::SNIPPET) {
+                    Some(Snippet::parse(self.insert_text()))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+pub type CompletionItems = Vec<CompletionItem>;
+
+#[derive(Debug)]
+pub struct CompletionResponse {
+    pub items: Vec<CompletionItem>,
+    pub incomplete: bool,
+    pub provider: CompletionProvider,
+    /// The document's completion-request revision (see `Trigger::revision`)
+    /// that was current when this response's request was dispatched.
+    /// `handle_response` compares this against the latest revision to drop
+    /// responses superseded by a newer trigger.
+    pub revision: usize,
+}
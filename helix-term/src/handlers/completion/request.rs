@@ -0,0 +1,293 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arc_swap::ArcSwap;
+use helix_core::completion::CompletionProvider;
+use helix_core::syntax::LanguageServerFeature;
+use helix_event::{AsyncHook, TaskController, TaskHandle};
+use helix_lsp::lsp;
+use helix_view::handlers::lsp::CompletionEvent;
+use helix_view::{Config, DocumentId, Editor, ViewId};
+use once_cell::sync::Lazy;
+use tokio::task::{JoinHandle, JoinSet};
+
+use super::item::{CompletionItem, CompletionResponse, LspCompletionItem};
+use super::{replace_completions, show_completion};
+use crate::compositor::Compositor;
+use crate::job::dispatch_blocking;
+
+/// Debounce delay (in ms) used when `completion_debounce_ms` isn't
+/// configured.
+const DEFAULT_DEBOUNCE_MS: u64 = 150;
+
+/// Per-document completion-request revision counters, so a keystroke in one
+/// document can't invalidate a still-valid, not-yet-displayed completion
+/// response for a different, untouched document (e.g. in another split).
+static REVISIONS: Lazy<Mutex<HashMap<DocumentId, Arc<AtomicUsize>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// The task currently populating the completion menu (or incomplete-list)
+/// for a document, if any, so a newer request for that same document can
+/// actually cancel it instead of leaving it to run its LSP round trip to
+/// completion in the background. Aborting the task drops its `JoinSet` of
+/// per-provider requests, which in turn aborts those.
+static IN_FLIGHT: Lazy<Mutex<HashMap<DocumentId, JoinHandle<()>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Returns (creating if necessary) the shared revision counter for `doc`.
+fn revision(doc: DocumentId) -> Arc<AtomicUsize> {
+    REVISIONS
+        .lock()
+        .unwrap()
+        .entry(doc)
+        .or_insert_with(|| Arc::new(AtomicUsize::new(0)))
+        .clone()
+}
+
+/// Spawns `replace_completions` for `requests` and registers it as `doc`'s
+/// in-flight task, aborting whatever was previously registered for it.
+fn spawn_replace_completions(
+    doc: DocumentId,
+    handle: TaskHandle,
+    requests: JoinSet<CompletionResponse>,
+    incomplete: bool,
+) {
+    let task = tokio::spawn(replace_completions(handle, requests, incomplete, revision(doc)));
+    if let Some(previous) = IN_FLIGHT.lock().unwrap().insert(doc, task) {
+        previous.abort();
+    }
+}
+
+/// Drops `doc`'s entries from the revision and in-flight registries, aborting
+/// its in-flight task if it still had one. Called when `doc` is closed so a
+/// long-lived session opening and closing many buffers doesn't grow these
+/// maps unboundedly.
+pub(crate) fn forget_document(doc: DocumentId) {
+    REVISIONS.lock().unwrap().remove(&doc);
+    if let Some(task) = IN_FLIGHT.lock().unwrap().remove(&doc) {
+        task.abort();
+    }
+}
+
+/// Identifies the edit location and document state that produced a
+/// completion request, so stale in-flight requests and responses can be
+/// recognized and dropped once the user keeps typing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Trigger {
+    pub pos: usize,
+    pub view: ViewId,
+    pub doc: DocumentId,
+    /// The value this trigger took from the document's completion-request
+    /// revision counter (`REVISIONS`). That counter is bumped by each new
+    /// trigger, incomplete-list re-request and cancellation, not by document
+    /// edits as such. Stamped onto every `CompletionResponse` dispatched for
+    /// it so `handle_response` can drop responses superseded by a newer
+    /// trigger.
+    pub revision: usize,
+}
+
+/// Debounces `CompletionEvent`s and coalesces in-flight LSP requests by
+/// document revision: a fresh (non-trigger-char) keystroke resets the
+/// debounce timer before a request is dispatched at all, trigger-char
+/// events bypass the debounce for responsiveness, and starting a new
+/// request cancels whatever was still in flight for an older revision.
+pub struct CompletionHandler {
+    trigger: Option<Trigger>,
+    request_controller: TaskController,
+    /// The document the most recently seen trigger targeted, so
+    /// `Cancel`/`DeleteText` (which carry no document of their own) know
+    /// which document's revision to bump and in-flight task to abort.
+    current_doc: Option<DocumentId>,
+    /// Shared with the `Editor` so `completion_debounce_ms` edits at
+    /// runtime take effect without reconstructing the handler.
+    config: Arc<ArcSwap<Config>>,
+}
+
+impl CompletionHandler {
+    pub fn new(config: Arc<ArcSwap<Config>>) -> CompletionHandler {
+        CompletionHandler {
+            trigger: None,
+            request_controller: TaskController::new(),
+            current_doc: None,
+            config,
+        }
+    }
+
+    fn debounce_ms(&self) -> u64 {
+        self.config
+            .load()
+            .completion_debounce_ms
+            .unwrap_or(DEFAULT_DEBOUNCE_MS)
+    }
+
+    fn next_trigger(&mut self, pos: usize, doc: DocumentId, view: ViewId) -> Trigger {
+        let revision = revision(doc).fetch_add(1, Ordering::SeqCst) + 1;
+        self.current_doc = Some(doc);
+        Trigger {
+            pos,
+            view,
+            doc,
+            revision,
+        }
+    }
+}
+
+impl AsyncHook for CompletionHandler {
+    type Event = CompletionEvent;
+
+    fn handle_event(&mut self, event: Self::Event, timeout: Option<Instant>) -> Option<Instant> {
+        match event {
+            CompletionEvent::AutoTrigger { cursor, doc, view } => {
+                self.trigger = Some(self.next_trigger(cursor, doc, view));
+                // Reset the debounce delay on every plain keystroke.
+                Some(Instant::now() + Duration::from_millis(self.debounce_ms()))
+            }
+            CompletionEvent::TriggerChar { cursor, doc, view } => {
+                self.trigger = Some(self.next_trigger(cursor, doc, view));
+                // Trigger characters bypass the debounce entirely for
+                // responsiveness (e.g. `.` should show members immediately).
+                self.finish_debounce();
+                None
+            }
+            CompletionEvent::Cancel | CompletionEvent::DeleteText { .. } => {
+                if let Some(doc) = self.current_doc.take() {
+                    revision(doc).fetch_add(1, Ordering::SeqCst);
+                    if let Some(task) = IN_FLIGHT.lock().unwrap().remove(&doc) {
+                        task.abort();
+                    }
+                }
+                self.trigger = None;
+                timeout
+            }
+        }
+    }
+
+    fn finish_debounce(&mut self) {
+        let Some(trigger) = self.trigger.take() else {
+            return;
+        };
+        let handle = self.request_controller.restart();
+        dispatch_blocking(move |editor, compositor| {
+            request_completions(editor, compositor, trigger, handle);
+        });
+    }
+}
+
+/// Builds and dispatches the real per-language-server
+/// `textDocument/completion` requests for `trigger`, opens an (initially
+/// empty) completion popup so the user sees something immediately, and
+/// hands the pending requests to `replace_completions` so they populate it
+/// as responses arrive. Each resulting `CompletionResponse` is stamped with
+/// `trigger.revision` so a response superseded by a newer trigger is
+/// dropped when it arrives, before it is dispatched to the editor.
+fn request_completions(
+    editor: &mut Editor,
+    compositor: &mut Compositor,
+    trigger: Trigger,
+    handle: TaskHandle,
+) {
+    let requests = build_completion_requests(editor, trigger);
+    if requests.len() == 0 {
+        return;
+    }
+
+    let (Some(doc), Some(view)) = (
+        editor.document(trigger.doc),
+        editor.tree.try_get(trigger.view),
+    ) else {
+        return;
+    };
+    let savepoint = doc.savepoint(view);
+
+    show_completion(
+        editor,
+        compositor,
+        Vec::new(),
+        Default::default(),
+        trigger,
+        savepoint,
+    );
+    spawn_replace_completions(trigger.doc, handle, requests, false);
+}
+
+/// Sends a `textDocument/completion` request to every language server of
+/// `trigger.doc` that supports completion, tagging each response with
+/// `trigger.revision`.
+fn build_completion_requests(editor: &mut Editor, trigger: Trigger) -> JoinSet<CompletionResponse> {
+    let mut requests = JoinSet::new();
+    let Some(doc) = editor.document(trigger.doc) else {
+        return requests;
+    };
+
+    for ls in doc.language_servers_with_feature(LanguageServerFeature::Completion) {
+        let provider = CompletionProvider::Lsp(ls.id());
+        let offset_encoding = ls.offset_encoding();
+        let pos = helix_lsp::util::pos_to_lsp_pos(doc.text(), trigger.pos, offset_encoding);
+        let doc_identifier = doc.identifier();
+        let context = helix_lsp::lsp::CompletionContext {
+            trigger_kind: helix_lsp::lsp::CompletionTriggerKind::INVOKED,
+            trigger_character: None,
+        };
+        let Some(future) = ls.completion(doc_identifier, pos, None, context) else {
+            continue;
+        };
+        let revision = trigger.revision;
+
+        requests.spawn(async move {
+            let response: Option<helix_lsp::lsp::CompletionResponse> = future
+                .await
+                .ok()
+                .and_then(|json| serde_json::from_value(json).ok());
+            let (items, incomplete) = match response {
+                Some(helix_lsp::lsp::CompletionResponse::Array(items)) => (items, false),
+                Some(helix_lsp::lsp::CompletionResponse::List(list)) => {
+                    (list.items, list.is_incomplete)
+                }
+                None => (Vec::new(), false),
+            };
+
+            let items = items
+                .into_iter()
+                .map(|item| {
+                    CompletionItem::Lsp(LspCompletionItem {
+                        item,
+                        provider,
+                        provider_priority: 0,
+                        resolved: false,
+                    })
+                })
+                .collect();
+
+            CompletionResponse {
+                items,
+                incomplete,
+                provider,
+                revision,
+            }
+        });
+    }
+
+    requests
+}
+
+/// Re-requests completions for an incomplete list, e.g. once the user has
+/// typed further and the previous response said more items might match now.
+/// Dispatched outside of `CompletionHandler`'s debounce since it reacts to
+/// an existing, already-shown completion menu rather than a fresh trigger.
+pub fn request_incomplete_completion_list(
+    editor: &mut Editor,
+    ui: &mut crate::ui::Completion,
+    handle: TaskHandle,
+) {
+    let Some(trigger) = ui.trigger() else {
+        return;
+    };
+    let trigger = Trigger {
+        revision: revision(trigger.doc).fetch_add(1, Ordering::SeqCst) + 1,
+        ..trigger
+    };
+    let requests = build_completion_requests(editor, trigger);
+    spawn_replace_completions(trigger.doc, handle, requests, true);
+}
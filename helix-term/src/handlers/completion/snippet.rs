@@ -0,0 +1,316 @@
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+use helix_core::{Range, Selection, Tendril};
+use helix_view::{DocumentId, ViewId};
+
+/// A single piece of a parsed LSP snippet body.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    /// Literal text copied verbatim into the rendered snippet.
+    Text(String),
+    /// A tabstop (`$1`, `${2:default}`, `$0`). Tabstops sharing the same
+    /// `index` are mirrors: they render with the same default text and stay
+    /// in sync while the user types in the active one.
+    Tabstop { index: u32, default: String },
+}
+
+/// A snippet body parsed into literal text plus ordered tabstops. `$0`
+/// (the final cursor position) keeps index 0 but is sorted after every
+/// other tabstop by `render`, so it is always visited last.
+#[derive(Debug, Clone, Default)]
+pub struct Snippet {
+    segments: Vec<Segment>,
+}
+
+impl Snippet {
+    /// Parses an LSP snippet body (`InsertTextFormat::Snippet`). Unsupported
+    /// constructs (variables, choices, transforms) are treated as literal
+    /// text rather than rejected outright, matching the permissive style of
+    /// the rest of the completion pipeline.
+    pub fn parse(body: &str) -> Snippet {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = body.char_indices().peekable();
+
+        while let Some((_, c)) = chars.next() {
+            if c != '$' {
+                text.push(c);
+                continue;
+            }
+
+            let Some(&(_, next)) = chars.peek() else {
+                text.push(c);
+                continue;
+            };
+
+            if next.is_ascii_digit() {
+                chars.next();
+                let mut digits = String::from(next);
+                while let Some(&(_, d)) = chars.peek() {
+                    if d.is_ascii_digit() {
+                        digits.push(d);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                flush_text(&mut segments, &mut text);
+                segments.push(Segment::Tabstop {
+                    index: digits.parse().unwrap_or(0),
+                    default: String::new(),
+                });
+            } else if next == '{' {
+                chars.next();
+                let mut inner = String::new();
+                for (_, c) in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    inner.push(c);
+                }
+                let (index_str, default) = inner.split_once(':').unwrap_or((inner.as_str(), ""));
+                if let Ok(index) = index_str.parse() {
+                    flush_text(&mut segments, &mut text);
+                    segments.push(Segment::Tabstop {
+                        index,
+                        default: default.to_string(),
+                    });
+                } else {
+                    // Not a tabstop (e.g. a variable like `${TM_SELECTED_TEXT}`):
+                    // keep the raw text so nothing is silently dropped.
+                    text.push('$');
+                    text.push('{');
+                    text.push_str(&inner);
+                    text.push('}');
+                }
+            } else {
+                text.push(c);
+            }
+        }
+        flush_text(&mut segments, &mut text);
+        Snippet { segments }
+    }
+
+    /// Renders the snippet starting at `char_offset` (a char position in the
+    /// document, matching `helix_core::Range`), returning the literal text
+    /// to insert plus the *char* ranges of each tabstop, grouped and ordered
+    /// by tabstop index with mirrors sharing an entry. `$0` always sorts
+    /// last regardless of its literal index.
+    pub fn render(&self, char_offset: usize) -> (Tendril, Vec<(u32, Vec<(usize, usize)>)>) {
+        let mut rendered = Tendril::new();
+        let mut char_len = 0;
+        let mut by_index: Vec<(u32, Vec<(usize, usize)>)> = Vec::new();
+
+        for segment in &self.segments {
+            match segment {
+                Segment::Text(text) => {
+                    rendered.push_str(text);
+                    char_len += text.chars().count();
+                }
+                Segment::Tabstop { index, default } => {
+                    // Every mirror renders the first non-empty default given
+                    // for its index, so `${1:name} = $1` renders `name = name`.
+                    let default = self
+                        .segments
+                        .iter()
+                        .find_map(|segment| match segment {
+                            Segment::Tabstop { index: i, default: d }
+                                if i == index && !d.is_empty() =>
+                            {
+                                Some(d.as_str())
+                            }
+                            _ => None,
+                        })
+                        .unwrap_or(default.as_str());
+                    let start = char_offset + char_len;
+                    rendered.push_str(default);
+                    char_len += default.chars().count();
+                    let end = char_offset + char_len;
+                    match by_index.iter_mut().find(|(i, _)| i == index) {
+                        Some((_, ranges)) => ranges.push((start, end)),
+                        None => by_index.push((*index, vec![(start, end)])),
+                    }
+                }
+            }
+        }
+
+        by_index.sort_by_key(|(index, _)| if *index == 0 { u32::MAX } else { *index });
+        (rendered, by_index)
+    }
+}
+
+fn flush_text(segments: &mut Vec<Segment>, text: &mut String) {
+    if !text.is_empty() {
+        segments.push(Segment::Text(std::mem::take(text)));
+    }
+}
+
+/// Tracks an in-progress snippet edit after a snippet completion item has
+/// been accepted, letting the user cycle the primary selection (and its
+/// mirrors) through `$1, $2, ..., $0`.
+#[derive(Debug, Clone)]
+pub struct SnippetSession {
+    pub doc: DocumentId,
+    pub view: ViewId,
+    /// Tabstop ranges in tabstop-index order (`$1, $2, ...`), with `$0` last.
+    /// Each entry holds every mirror range sharing that tabstop's index.
+    tabstops: Vec<Vec<(usize, usize)>>,
+    active: usize,
+    /// The document's `len_chars()` as of the last edit recorded against
+    /// this session (via `apply_edit`, kept up to date below, or the
+    /// `doc_len` passed to `new` at render time). A post-command hook that
+    /// only sees the buffer's current length — not a clean before/after
+    /// diff for the command it just ran — can diff against this to recover
+    /// the edit's real size, or learn that nothing was actually edited.
+    doc_len: usize,
+}
+
+impl SnippetSession {
+    pub fn new(
+        doc: DocumentId,
+        view: ViewId,
+        tabstops: Vec<(u32, Vec<(usize, usize)>)>,
+        doc_len: usize,
+    ) -> Self {
+        SnippetSession {
+            doc,
+            view,
+            tabstops: tabstops.into_iter().map(|(_, ranges)| ranges).collect(),
+            active: 0,
+            doc_len,
+        }
+    }
+
+    /// The buffer-length delta since the last edit recorded against this
+    /// session, or `None` if the length hasn't changed — i.e. whatever
+    /// command just ran was pure cursor movement rather than an edit, and
+    /// the session should be left alone instead of resynced or torn down.
+    pub fn external_len_diff(&self, current_len: usize) -> Option<isize> {
+        let diff = current_len as isize - self.doc_len as isize;
+        (diff != 0).then_some(diff)
+    }
+
+    /// Selection covering every mirror of the currently active tabstop.
+    pub fn selection(&self, text_len: usize) -> Option<Selection> {
+        let ranges = self.tabstops.get(self.active)?;
+        let ranges: Vec<_> = ranges
+            .iter()
+            .map(|&(start, end)| Range::new(start.min(text_len), end.min(text_len)))
+            .collect();
+        Some(Selection::new(ranges.into(), 0))
+    }
+
+    /// Advances to the next tabstop. Returns `false` once `$0` (always the
+    /// last entry) has already been reached, so the caller tears the
+    /// session down instead of wrapping back to `$1`.
+    pub fn goto_next(&mut self) -> bool {
+        if self.active + 1 >= self.tabstops.len() {
+            return false;
+        }
+        self.active += 1;
+        true
+    }
+
+    pub fn goto_prev(&mut self) -> bool {
+        if self.active == 0 {
+            return false;
+        }
+        self.active -= 1;
+        true
+    }
+
+    /// Whether `pos` falls inside any mirror of the active tabstop; used
+    /// to decide whether an edit keeps the session alive or invalidates it.
+    pub fn contains(&self, pos: usize) -> bool {
+        self.tabstops.get(self.active).is_some_and(|ranges| {
+            ranges
+                .iter()
+                .any(|&(start, end)| pos >= start && pos <= end)
+        })
+    }
+
+    /// Keeps tabstop ranges in sync with an edit of `len_diff` chars
+    /// (positive for an insertion, negative for a deletion) made at `at`.
+    /// An edit landing inside the active tabstop grows/shrinks every mirror
+    /// of that tabstop together so they stay sized identically; an edit
+    /// elsewhere just shifts the ranges that came after it. Mirrors' text
+    /// is already kept identical by the editor itself — `cycle_snippet_tabstop`
+    /// selects every mirror range, and Helix's edit commands apply to every
+    /// range in the current selection — so this only needs to update range
+    /// bookkeeping, not replay any content.
+    pub fn apply_edit(&mut self, at: usize, len_diff: isize) {
+        let active = self.active;
+        for (index, ranges) in self.tabstops.iter_mut().enumerate() {
+            for range in ranges.iter_mut() {
+                if index == active && at >= range.0 && at <= range.1 {
+                    range.1 = shifted(range.1, len_diff);
+                } else if range.0 >= at {
+                    range.0 = shifted(range.0, len_diff);
+                    range.1 = shifted(range.1, len_diff);
+                } else if range.1 >= at {
+                    range.1 = shifted(range.1, len_diff);
+                }
+            }
+        }
+        self.doc_len = shifted(self.doc_len, len_diff);
+    }
+}
+
+fn shifted(pos: usize, len_diff: isize) -> usize {
+    (pos as isize + len_diff).max(0) as usize
+}
+
+/// The in-progress snippet session, if any. Helix runs a single `Editor`
+/// per process, so (mirroring the process-wide frecency store) this is
+/// tracked as a static rather than threaded through every call site that
+/// might need to invalidate it.
+static ACTIVE_SESSION: Lazy<Mutex<Option<SnippetSession>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn active() -> Option<SnippetSession> {
+    ACTIVE_SESSION.lock().unwrap().clone()
+}
+
+pub fn set_active(session: Option<SnippetSession>) {
+    *ACTIVE_SESSION.lock().unwrap() = session;
+}
+
+pub fn with_active<T>(f: impl FnOnce(&mut SnippetSession) -> T) -> Option<T> {
+    ACTIVE_SESSION.lock().unwrap().as_mut().map(f)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parses_plain_tabstops() {
+        let snippet = Snippet::parse("fn $1() {\n    $0\n}");
+        let (rendered, tabstops) = snippet.render(0);
+        assert_eq!(rendered.as_str(), "fn () {\n    \n}");
+        let indices: Vec<u32> = tabstops.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![1, 0]);
+    }
+
+    #[test]
+    fn parses_default_text_and_mirrors() {
+        let snippet = Snippet::parse("${1:name}: ${1:name} = ${2:value}");
+        let (rendered, tabstops) = snippet.render(0);
+        assert_eq!(rendered.as_str(), "name: name = value");
+        assert_eq!(tabstops.len(), 2);
+        assert_eq!(
+            tabstops[0].1.len(),
+            2,
+            "mirrors of $1 should both be tracked"
+        );
+    }
+
+    #[test]
+    fn final_tabstop_sorts_last() {
+        let snippet = Snippet::parse("$0 before $2 and $1");
+        let (_, tabstops) = snippet.render(0);
+        let indices: Vec<u32> = tabstops.iter().map(|(i, _)| *i).collect();
+        assert_eq!(indices, vec![1, 2, 0]);
+    }
+}